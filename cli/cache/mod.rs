@@ -4,6 +4,8 @@ use crate::errors::get_error_class_name;
 use crate::file_fetcher::FileFetcher;
 use crate::npm;
 
+use deno_core::error::custom_error;
+use deno_core::error::AnyError;
 use deno_core::futures;
 use deno_core::futures::FutureExt;
 use deno_core::ModuleSpecifier;
@@ -12,6 +14,8 @@ use deno_graph::source::LoadFuture;
 use deno_graph::source::LoadResponse;
 use deno_graph::source::Loader;
 use deno_runtime::permissions::Permissions;
+use serde::Deserialize;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -39,6 +43,135 @@ pub use parsed_source::ParsedSourceCache;
 /// Permissions used to save a file in the disk caches.
 pub const CACHE_PERM: u32 = 0o644;
 
+/// A pluggable, team-shared cache for remote module source bodies,
+/// consulted by [`FetchCacher::load`] ahead of the local `DENO_DIR`. A hit
+/// is served directly (and written through to the local [`HttpCache`] so
+/// it's found on disk afterward, e.g. by `get_cache_info()`); a miss falls
+/// through to the existing local-disk/network path, which then populates
+/// the backend. When the loader is offline the backend is neither read nor
+/// written.
+///
+/// Scope: this only covers the source bodies `FetchCacher::load` hands to
+/// the graph. It deliberately does not abstract `HttpCache`, `DiskCache`,
+/// or `EmitCache` themselves — they still read and write the local
+/// `DENO_DIR` directly — and it does not share emitted (transpiled/
+/// type-checked) output. Sharing emitted output would mean threading this
+/// trait through `EmitCache` and consulting it from `get_cache_info()`,
+/// which is a separate, larger change than a source-body cache.
+pub trait RemoteModuleCache: std::fmt::Debug + Send + Sync {
+  /// Returns the bytes stored under `key`, if any.
+  fn read(&self, key: &str) -> Result<Option<Vec<u8>>, AnyError>;
+  /// Stores `bytes` under `key`, overwriting any existing entry.
+  fn write(&self, key: &str, bytes: &[u8]) -> Result<(), AnyError>;
+}
+
+/// The key a [`RemoteModuleCache`] backend stores a module under.
+fn remote_cache_key(specifier: &ModuleSpecifier) -> String {
+  format!("source:{specifier}")
+}
+
+/// Only `http`/`https` specifiers are ever fetched remotely, so only those
+/// are eligible for offline-mode resolution or a [`RemoteModuleCache`] backend;
+/// `file:` and other local specifiers always go through the normal loader
+/// path, which never touches the network.
+fn is_remote_specifier(specifier: &ModuleSpecifier) -> bool {
+  matches!(specifier.scheme(), "http" | "https")
+}
+
+/// The error `load()` returns for a remote specifier that isn't present in
+/// the local http cache while running offline. Distinguishable by error
+/// class from the "NotFound" produced for a specifier that simply doesn't
+/// exist at all.
+fn not_cached_error(specifier: &ModuleSpecifier) -> AnyError {
+  custom_error(
+    "NotCached",
+    format!(
+      "Uncached remote module: \"{specifier}\". Modules must already be present in the cache when loading offline."
+    ),
+  )
+}
+
+/// The value persisted in a [`RemoteModuleCache`] backend for a cached module,
+/// bundling the body together with the headers it was served with so that a
+/// remote-cache hit still carries media-type hints for `deno_graph`.
+#[derive(Serialize, Deserialize)]
+struct CachedModule {
+  content: String,
+  maybe_headers: Option<HashMap<String, String>>,
+}
+
+/// Computes a digest of `content` using `algorithm` ("sha256" or "sha512"),
+/// returning its lowercase hex encoding.
+fn digest_hex(algorithm: &str, content: &[u8]) -> Result<String, AnyError> {
+  match algorithm {
+    "sha256" => Ok(crate::checksum::gen(&[content])),
+    "sha512" => {
+      let hash = ring::digest::digest(&ring::digest::SHA512, content);
+      Ok(hash.as_ref().iter().map(|b| format!("{b:02x}")).collect())
+    }
+    other => Err(custom_error(
+      "IntegrityError",
+      format!(
+        "Unsupported checksum algorithm \"{other}\". Supported algorithms: sha256, sha512."
+      ),
+    )),
+  }
+}
+
+/// Verifies `content`'s digest against the expected checksum registered for
+/// `specifier` in `maybe_checksums`, if any. A specifier with no registered
+/// checksum always passes.
+///
+/// Checksums are in `<algorithm>:<hex digest>` form, e.g.
+/// `sha256:2c26b46b...` or `sha512:0d8e3cb9...`; the hex portion is compared
+/// case-insensitively.
+fn verify_checksum(
+  maybe_checksums: Option<&HashMap<ModuleSpecifier, String>>,
+  specifier: &ModuleSpecifier,
+  content: &[u8],
+) -> Result<(), AnyError> {
+  let expected = match maybe_checksums.and_then(|checksums| checksums.get(specifier)) {
+    Some(expected) => expected,
+    None => return Ok(()),
+  };
+  let (algorithm, expected_hex) = expected.split_once(':').ok_or_else(|| {
+    custom_error(
+      "IntegrityError",
+      format!(
+        "Invalid checksum \"{expected}\" registered for \"{specifier}\": expected \"<algorithm>:<hex digest>\"."
+      ),
+    )
+  })?;
+  let actual_hex = digest_hex(algorithm, content)?;
+  if !actual_hex.eq_ignore_ascii_case(expected_hex) {
+    return Err(custom_error(
+      "IntegrityError",
+      format!(
+        "Integrity check failed for \"{specifier}\".\n  expected: {algorithm}:{expected_hex}\n  actual:   {algorithm}:{actual_hex}"
+      ),
+    ));
+  }
+  Ok(())
+}
+
+fn maybe_extend_optional_map(
+  maybe_map: Option<&HashMap<String, String>>,
+  maybe_extend: Option<&HashMap<String, String>>,
+) -> Option<HashMap<String, String>> {
+  if maybe_map.is_none() && maybe_extend.is_none() {
+    None
+  } else {
+    let mut headers = HashMap::<String, String>::new();
+    if let Some(map) = maybe_map {
+      headers.extend(map.clone());
+    }
+    if let Some(extend) = maybe_extend {
+      headers.extend(extend.clone());
+    }
+    Some(headers)
+  }
+}
+
 /// A "wrapper" for the FileFetcher and DiskCache for the Deno CLI that provides
 /// a concise interface to the DENO_DIR when building module graphs.
 pub struct FetchCacher {
@@ -46,16 +179,36 @@ pub struct FetchCacher {
   dynamic_permissions: Permissions,
   file_fetcher: Arc<FileFetcher>,
   file_header_overrides: HashMap<ModuleSpecifier, HashMap<String, String>>,
+  http_cache: HttpCache,
+  /// Expected cryptographic digests, in `<algorithm>:<hex digest>` form
+  /// (e.g. `sha256:2c26b46b...` or `sha512:0d8e3cb9...`), for specifiers
+  /// that should be pinned independent of the lockfile. Checked in `load()`
+  /// before a module's content is handed to the graph.
+  maybe_checksums: Option<HashMap<ModuleSpecifier, String>>,
+  /// When `true`, `load()` never hits the network: remote specifiers are
+  /// resolved strictly from the on-disk http cache, and a specifier that
+  /// isn't cached yet fails with a "NotCached" error instead of being
+  /// fetched.
+  offline: bool,
+  /// An optional shared cache backend for remote module source bodies,
+  /// consulted before the local disk cache and network. A hit is served
+  /// directly; a miss falls through to the existing local/network path,
+  /// which then populates the backend. Not consulted when `offline` is set.
+  maybe_remote_cache: Option<Arc<dyn RemoteModuleCache>>,
   root_permissions: Permissions,
 }
 
 impl FetchCacher {
   pub fn new(
     emit_cache: EmitCache,
+    http_cache: HttpCache,
     file_fetcher: FileFetcher,
     file_header_overrides: HashMap<ModuleSpecifier, HashMap<String, String>>,
     root_permissions: Permissions,
     dynamic_permissions: Permissions,
+    offline: bool,
+    maybe_checksums: Option<HashMap<ModuleSpecifier, String>>,
+    maybe_remote_cache: Option<Arc<dyn RemoteModuleCache>>,
   ) -> Self {
     let file_fetcher = Arc::new(file_fetcher);
 
@@ -64,9 +217,44 @@ impl FetchCacher {
       dynamic_permissions,
       file_fetcher,
       file_header_overrides,
+      http_cache,
+      maybe_checksums,
+      maybe_remote_cache,
+      offline,
       root_permissions,
     }
   }
+
+  /// Loads `specifier` directly from the local cache, without ever touching
+  /// the network. Used when the loader is running in offline mode.
+  ///
+  /// This drives `FileFetcher::fetch_cached` rather than reading the http
+  /// cache directly, so a specifier that was cached as a redirect is
+  /// followed to its final cached target the same way the online path
+  /// would follow it live.
+  fn load_from_http_cache(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Result<Option<LoadResponse>, AnyError> {
+    let file = match self.file_fetcher.fetch_cached(specifier, 10)? {
+      Some(file) => file,
+      None => return Err(not_cached_error(specifier)),
+    };
+    verify_checksum(
+      self.maybe_checksums.as_ref(),
+      specifier,
+      file.source.as_bytes(),
+    )?;
+    let maybe_headers = maybe_extend_optional_map(
+      file.maybe_headers.as_ref(),
+      self.file_header_overrides.get(specifier),
+    );
+    Ok(Some(LoadResponse::Module {
+      specifier: file.specifier,
+      maybe_headers,
+      content: file.source,
+    }))
+  }
 }
 
 impl Loader for FetchCacher {
@@ -96,24 +284,6 @@ impl Loader for FetchCacher {
     specifier: &ModuleSpecifier,
     is_dynamic: bool,
   ) -> LoadFuture {
-    fn maybe_extend_optional_map(
-      maybe_map: Option<&HashMap<String, String>>,
-      maybe_extend: Option<&HashMap<String, String>>,
-    ) -> Option<HashMap<String, String>> {
-      if maybe_map.is_none() && maybe_extend.is_none() {
-        None
-      } else {
-        let mut headers = HashMap::<String, String>::new();
-        if let Some(map) = maybe_map {
-          headers.extend(map.clone());
-        }
-        if let Some(extend) = maybe_extend {
-          headers.extend(extend.clone());
-        }
-        Some(headers)
-      }
-    }
-
     if specifier.scheme() == "npm" {
       return Box::pin(futures::future::ready(
         match npm::NpmPackageReference::from_specifier(specifier) {
@@ -125,6 +295,51 @@ impl Loader for FetchCacher {
       ));
     }
 
+    let is_remote = is_remote_specifier(specifier);
+
+    if is_remote && !self.offline {
+      if let Some(remote_cache) = &self.maybe_remote_cache {
+        // A backend hit whose payload doesn't parse is treated the same as
+        // a miss: fall through to the local/network path below rather than
+        // failing the whole load over a corrupt shared-cache entry.
+        let maybe_cached = remote_cache
+          .read(&remote_cache_key(specifier))
+          .ok()
+          .flatten()
+          .and_then(|bytes| serde_json::from_slice::<CachedModule>(&bytes).ok());
+        if let Some(cached) = maybe_cached {
+          let result = verify_checksum(
+            self.maybe_checksums.as_ref(),
+            specifier,
+            cached.content.as_bytes(),
+          )
+          .map(|_| {
+            let _ = self.http_cache.set(
+              specifier,
+              cached.maybe_headers.clone().unwrap_or_default(),
+              cached.content.as_bytes(),
+            );
+            let maybe_headers = maybe_extend_optional_map(
+              cached.maybe_headers.as_ref(),
+              self.file_header_overrides.get(specifier),
+            );
+            Some(LoadResponse::Module {
+              specifier: specifier.clone(),
+              maybe_headers,
+              content: cached.content.into(),
+            })
+          });
+          return Box::pin(futures::future::ready(result));
+        }
+      }
+    }
+
+    if is_remote && self.offline {
+      return Box::pin(futures::future::ready(
+        self.load_from_http_cache(specifier),
+      ));
+    }
+
     let specifier = specifier.clone();
     let mut permissions = if is_dynamic {
       self.dynamic_permissions.clone()
@@ -133,6 +348,8 @@ impl Loader for FetchCacher {
     };
     let file_fetcher = self.file_fetcher.clone();
     let file_header_overrides = self.file_header_overrides.clone();
+    let maybe_checksums = self.maybe_checksums.clone();
+    let maybe_remote_cache = self.maybe_remote_cache.clone();
 
     async move {
       file_fetcher
@@ -150,6 +367,25 @@ impl Loader for FetchCacher {
             Err(err)
           },
           |file| {
+            verify_checksum(
+              maybe_checksums.as_ref(),
+              &specifier,
+              file.source.as_bytes(),
+            )?;
+
+            if is_remote {
+              if let Some(remote_cache) = &maybe_remote_cache {
+                let cached = CachedModule {
+                  content: file.source.to_string(),
+                  maybe_headers: file.maybe_headers.clone(),
+                };
+                if let Ok(bytes) = serde_json::to_vec(&cached) {
+                  let _ =
+                    remote_cache.write(&remote_cache_key(&specifier), &bytes);
+                }
+              }
+            }
+
             let maybe_overridden_headers =
               file_header_overrides.get(&specifier);
 
@@ -169,3 +405,117 @@ impl Loader for FetchCacher {
     .boxed()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn specifier() -> ModuleSpecifier {
+    ModuleSpecifier::parse("https://example.com/mod.ts").unwrap()
+  }
+
+  #[test]
+  fn is_remote_specifier_only_matches_http_and_https() {
+    assert!(is_remote_specifier(
+      &ModuleSpecifier::parse("https://example.com/mod.ts").unwrap()
+    ));
+    assert!(is_remote_specifier(
+      &ModuleSpecifier::parse("http://example.com/mod.ts").unwrap()
+    ));
+    assert!(!is_remote_specifier(
+      &ModuleSpecifier::parse("file:///mod.ts").unwrap()
+    ));
+    assert!(!is_remote_specifier(
+      &ModuleSpecifier::parse("npm:chalk").unwrap()
+    ));
+  }
+
+  #[test]
+  fn not_cached_error_is_distinct_from_not_found() {
+    let err = not_cached_error(&specifier());
+    assert_eq!(get_error_class_name(&err), "NotCached");
+    assert!(err.to_string().contains("Uncached remote module"));
+  }
+
+  #[test]
+  fn verify_checksum_passes_when_none_registered() {
+    assert!(verify_checksum(None, &specifier(), b"hello").is_ok());
+    let checksums = HashMap::new();
+    assert!(verify_checksum(Some(&checksums), &specifier(), b"hello").is_ok());
+  }
+
+  #[test]
+  fn verify_checksum_accepts_matching_sha256_digest() {
+    let spec = specifier();
+    let mut checksums = HashMap::new();
+    checksums.insert(
+      spec.clone(),
+      format!("sha256:{}", crate::checksum::gen(&[b"hello"])),
+    );
+    assert!(verify_checksum(Some(&checksums), &spec, b"hello").is_ok());
+  }
+
+  #[test]
+  fn verify_checksum_accepts_matching_sha512_digest() {
+    let spec = specifier();
+    let mut checksums = HashMap::new();
+    checksums.insert(
+      spec.clone(),
+      format!("sha512:{}", digest_hex("sha512", b"hello").unwrap()),
+    );
+    assert!(verify_checksum(Some(&checksums), &spec, b"hello").is_ok());
+  }
+
+  #[test]
+  fn verify_checksum_is_case_insensitive() {
+    let spec = specifier();
+    let mut checksums = HashMap::new();
+    let hex = crate::checksum::gen(&[b"hello"]).to_uppercase();
+    checksums.insert(spec.clone(), format!("sha256:{hex}"));
+    assert!(verify_checksum(Some(&checksums), &spec, b"hello").is_ok());
+  }
+
+  #[test]
+  fn verify_checksum_rejects_mismatched_digest() {
+    let spec = specifier();
+    let mut checksums = HashMap::new();
+    checksums.insert(spec.clone(), "sha256:0000000000000000".to_string());
+    let err =
+      verify_checksum(Some(&checksums), &spec, b"hello").unwrap_err();
+    assert_eq!(get_error_class_name(&err), "IntegrityError");
+  }
+
+  #[test]
+  fn verify_checksum_rejects_unsupported_algorithm() {
+    let spec = specifier();
+    let mut checksums = HashMap::new();
+    checksums.insert(spec.clone(), "md5:0000000000000000".to_string());
+    let err =
+      verify_checksum(Some(&checksums), &spec, b"hello").unwrap_err();
+    assert_eq!(get_error_class_name(&err), "IntegrityError");
+  }
+
+  #[test]
+  fn verify_checksum_rejects_missing_algorithm_prefix() {
+    let spec = specifier();
+    let mut checksums = HashMap::new();
+    checksums.insert(spec.clone(), "deadbeef".to_string());
+    let err =
+      verify_checksum(Some(&checksums), &spec, b"hello").unwrap_err();
+    assert_eq!(get_error_class_name(&err), "IntegrityError");
+  }
+
+  #[test]
+  fn cached_module_round_trips_through_json() {
+    let mut headers = HashMap::new();
+    headers.insert("content-type".to_string(), "application/typescript".to_string());
+    let cached = CachedModule {
+      content: "export const a = 1;".to_string(),
+      maybe_headers: Some(headers.clone()),
+    };
+    let bytes = serde_json::to_vec(&cached).unwrap();
+    let round_tripped: CachedModule = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(round_tripped.content, cached.content);
+    assert_eq!(round_tripped.maybe_headers, Some(headers));
+  }
+}